@@ -0,0 +1,47 @@
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression as GzCompression;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Error, Format};
+
+/// Gzip's own two-byte magic prefix, used to tell a compressed payload apart
+/// from one written without this wrapper.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Wraps a `Format`, gzip-compressing the bytes it produces and
+/// transparently decompressing them again on the way back in.
+///
+/// This is a pure layering over `Format`/`FileStorage`: `FileStorage<Compressed<Json>>`
+/// works exactly like `FileStorage<Json>`, just smaller on disk. Detection
+/// on read is via gzip's magic prefix, so a file written before compression
+/// was turned on still loads: bytes that don't start with the magic are
+/// handed to the inner `Format` uncompressed instead of failing.
+pub struct Compressed<F: Format>(PhantomData<F>);
+
+impl<F: Format> Format for Compressed<F> {
+    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+        let raw = F::serialize(value)?;
+        let mut encoder = GzEncoder::new(Vec::new(), GzCompression::default());
+        encoder
+            .write_all(&raw)
+            .map_err(|e| Error::Serialize(e.to_string()))?;
+        encoder.finish().map_err(|e| Error::Serialize(e.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            let mut raw = Vec::new();
+            GzDecoder::new(bytes)
+                .read_to_end(&mut raw)
+                .map_err(|e| Error::Deserialize(e.to_string()))?;
+            F::deserialize(&raw)
+        } else {
+            F::deserialize(bytes)
+        }
+    }
+}
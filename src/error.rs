@@ -0,0 +1,57 @@
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong opening, reading, or flushing a
+/// [`Database`](crate::Database).
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying file or filesystem operation failed.
+    Io(io::Error),
+    /// A stored payload couldn't be decoded.
+    Deserialize(String),
+    /// A value couldn't be encoded for storage.
+    Serialize(String),
+    /// Another handle already holds the file's advisory lock. Returned by
+    /// `FileStorage::from` (a non-blocking `try_lock`, not a hang) when a
+    /// different `Database` on the same path hasn't been dropped yet.
+    Locked,
+    /// The stored payload is tagged with a format version newer than this
+    /// build knows how to read.
+    UnknownVersion(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {e}"),
+            Error::Deserialize(e) => write!(f, "failed to deserialize: {e}"),
+            Error::Serialize(e) => write!(f, "failed to serialize: {e}"),
+            Error::Locked => write!(f, "database file is locked by another handle"),
+            Error::UnknownVersion(v) => write!(
+                f,
+                "database format version {v} is newer than this build understands"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Deserialize(e.to_string())
+    }
+}
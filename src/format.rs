@@ -0,0 +1,78 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::Error;
+
+/// An on-disk encoding.
+///
+/// This is deliberately kept separate from `Storage`: a `Storage` impl decides
+/// *where* the data lives (a file, memory, a database), while a `Format`
+/// decides *how* it's encoded once there. `FileStorage<F>` is generic over
+/// `Format`, so picking a different encoding never touches the storage logic.
+/// Generic over the value being encoded so the same trait covers both the
+/// whole `BTreeMap<String, String>` payload and individual typed values (see
+/// `Database::set_typed`/`get_typed`).
+pub trait Format {
+    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, Error>;
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error>;
+}
+
+/// Compact, human-readable JSON. The original (and still default) format.
+pub struct Json;
+
+impl Format for Json {
+    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+        serde_json::to_vec(value).map_err(|e| Error::Serialize(e.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+        serde_json::from_slice(bytes).map_err(Error::from)
+    }
+}
+
+/// RON (Rusty Object Notation). Human-editable, a little friendlier for
+/// hand-tweaking a database than JSON.
+pub struct Ron;
+
+impl Format for Ron {
+    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+        ron::to_string(value)
+            .map(|s| s.into_bytes())
+            .map_err(|e| Error::Serialize(e.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+        let text = std::str::from_utf8(bytes).map_err(|e| Error::Deserialize(e.to_string()))?;
+        ron::from_str(text).map_err(|e| Error::Deserialize(e.to_string()))
+    }
+}
+
+/// YAML. Human-editable, more forgiving about trailing whitespace/newlines
+/// than RON.
+pub struct Yaml;
+
+impl Format for Yaml {
+    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+        serde_yaml::to_string(value)
+            .map(|s| s.into_bytes())
+            .map_err(|e| Error::Serialize(e.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+        serde_yaml::from_slice(bytes).map_err(|e| Error::Deserialize(e.to_string()))
+    }
+}
+
+/// Bincode. Compact binary format, not human-readable, fastest to
+/// (de)serialize of the four.
+pub struct Bincode;
+
+impl Format for Bincode {
+    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+        bincode::serialize(value).map_err(|e| Error::Serialize(e.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+        bincode::deserialize(bytes).map_err(|e| Error::Deserialize(e.to_string()))
+    }
+}
@@ -1,28 +1,87 @@
-use std::io;
-use serde_json;
-use std::fs::{File};
-use std::collections::BTreeMap;
+mod compression;
+mod error;
+mod format;
+mod migration;
+mod storage;
+
 use std::ops::{Deref, DerefMut};
-use std::fmt::{Debug, Formatter};
-use std::io::{Seek, SeekFrom, Write};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub use compression::Compressed;
+pub use error::Error;
+pub use format::{Bincode, Format, Json, Ron, Yaml};
+pub use migration::{Migration, CURRENT_VERSION};
+#[cfg(feature = "sqlite")]
+pub use storage::SqliteStorage;
+pub use storage::{FileStorage, MemoryStorage};
 
 pub struct Database<S: Storage> {
     storage: S,
 }
 
+impl<S: Storage> Database<S> {
+    /// The on-disk format version this database's storage was loaded as,
+    /// after any migrations ran. See [`CURRENT_VERSION`].
+    pub fn schema_version(&self) -> u32 {
+        self.storage.schema_version()
+    }
+
+    /// Persists pending changes, surfacing any durability error instead of
+    /// only finding out about it (and panicking) when the `Database` drops.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.storage.flush()
+    }
+}
+
+impl<F: Format> Database<FileStorage<F>> {
+    /// Persists `value` through the database's active `Format`, for callers
+    /// who want to store structures instead of hand-serializing to a
+    /// `String` themselves. The encoded bytes are base64'd before going
+    /// through the plain string `Storage` API, so binary formats like
+    /// `Bincode` round-trip safely alongside the simple string API.
+    pub fn set_typed<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        let bytes = F::serialize(value)?;
+        self.storage.set(key, &BASE64.encode(bytes))
+    }
+
+    /// The typed counterpart to [`Database::set_typed`].
+    pub fn get_typed<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Error> {
+        let Some(raw) = self.storage.get(key) else {
+            return Ok(None);
+        };
+        let bytes = BASE64
+            .decode(raw)
+            .map_err(|e| Error::Deserialize(e.to_string()))?;
+        F::deserialize(&bytes).map(Some)
+    }
+}
+
 pub trait Storage: Sized {
-    fn from(source: &str) -> Result<Self, io::Error> where Self: Storage;
+    fn from(source: &str) -> Result<Self, Error> where Self: Storage;
 
-    fn flush(&mut self) -> Result<(), io::Error>;
+    fn flush(&mut self) -> Result<(), Error>;
 
     fn get(&self, key: &str) -> Option<&str>;
-    fn del(&mut self, key: &str) -> Result<(), io::Error>;
-    fn set(&mut self, key: &str, value: &str) -> Result<(), io::Error>;
+    fn del(&mut self, key: &str) -> Result<(), Error>;
+    fn set(&mut self, key: &str, value: &str) -> Result<(), Error>;
+
+    /// The on-disk format version, post-migration. Backends with no
+    /// versioned payload of their own (e.g. an in-memory store) can just
+    /// keep the default.
+    fn schema_version(&self) -> u32 {
+        CURRENT_VERSION
+    }
 }
 
 impl<S: Storage> Drop for Database<S> {
     fn drop(&mut self) {
-        self.storage.flush().expect("failed to flush");
+        // Best-effort: callers who need to observe a durability error should
+        // call `Database::flush` explicitly before dropping.
+        let _ = self.storage.flush();
     }
 }
 
@@ -40,76 +99,40 @@ impl<S: Storage> DerefMut for Database<S> {
     }
 }
 
-pub struct JSONStorage {
-    file: File,
-    data: BTreeMap<String, String>,
-}
-
-impl Debug for JSONStorage {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("JSONStorage")
-            .field("file", &self.file)
-            .field("data", &self.data)
-            .finish()
-    }
-}
-
-impl Storage for JSONStorage {
-    fn from(source: &str) -> Result<JSONStorage, io::Error> {
-        let file = File::options().create(true).read(true).write(true).open(source)?;
-        let data = if let Ok(map) = serde_json::from_reader(file.try_clone()?) {
-            map
-        } else {
-            BTreeMap::new()
-        };
-
-        Ok(JSONStorage {
-            data,
-            file: file.try_clone()?,
-        })
-    }
-
-    fn flush(&mut self) -> Result<(), io::Error> {
-        self.file.seek(SeekFrom::Start(0))?;
-        let content = serde_json::to_vec(&self.data).expect("should be able to serialize");
-        self.file.write_all(&content)?;
-        self.file.flush()
-    }
-
-    fn get(&self, key: &str) -> Option<&str> {
-        Some(self.data.get(key)?.as_str())
-    }
-
-    fn del(&mut self, key: &str) -> Result<(), io::Error> {
-        self.data.remove(key);
-        Ok(())
-    }
+/// The original JSON-backed storage, now a thin alias over `FileStorage<Json>`.
+pub type JSONStorage = FileStorage<Json>;
 
-    fn set(&mut self, key: &str, value: &str) -> Result<(), io::Error> {
-        self.data.insert(key.to_string(), value.to_string());
-        Ok(())
-    }
+pub fn open<S: Storage>(path: &str) -> Result<Database<S>, Error> {
+    Ok(Database { storage: S::from(path)? })
 }
 
-pub fn open<S: Storage>(path: &str) -> Result<Database<S>, io::Error> {
-    Ok(Database { storage: S::from(path)? })
+/// Loads the file at `path` (running any pending migrations, same as
+/// [`open`]) and immediately flushes it, rewriting it tagged with
+/// [`CURRENT_VERSION`]. Use this to upgrade a database in place without
+/// keeping it open.
+pub fn migrate_in_place<F: Format>(path: &str) -> Result<(), Error> {
+    let mut storage = <FileStorage<F> as Storage>::from(path)?;
+    storage.flush()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{JSONStorage, Storage};
+    use crate::{
+        Bincode, Compressed, Error, FileStorage, Json, JSONStorage, MemoryStorage, Ron, Storage,
+        Yaml,
+    };
 
     const DB_PATH: &str = "./shit.db";
 
     #[test]
     fn it_opens_a_database() {
-        let result = crate::open::<JSONStorage>(DB_PATH);
+        let result = crate::open::<JSONStorage>("./shit_open.db");
         assert_eq!(result.is_ok(), true);
     }
 
     #[test]
     fn it_sets_deletes_and_gets_values() {
-        let mut db = crate::open::<JSONStorage>(DB_PATH).unwrap();
+        let mut db = crate::open::<JSONStorage>("./shit_setdel.db").unwrap();
 
         let set_result = db.set("xixi", "haha");
 
@@ -144,4 +167,203 @@ mod tests {
         assert_eq!(big_result, Some("BBBBBBiiiiiigggggggggg"));
         assert_eq!(hehe_result, Some("heihei"));
     }
+
+    #[test]
+    fn it_persists_data_through_every_format() {
+        fn roundtrip<F: crate::Format>(path: &str) {
+            let _ = std::fs::remove_file(path);
+            let _ = std::fs::remove_file(format!("{path}.lock"));
+
+            let mut db = crate::open::<FileStorage<F>>(path).unwrap();
+            let _ = db.set("xixi", "haha");
+            db.flush().unwrap();
+            drop(db);
+
+            let db = crate::open::<FileStorage<F>>(path).unwrap();
+            assert_eq!(db.get("xixi"), Some("haha"));
+        }
+
+        roundtrip::<Ron>("./shit_ron.db");
+        roundtrip::<Yaml>("./shit_yaml.db");
+        roundtrip::<Bincode>("./shit_bincode.db");
+    }
+
+    #[test]
+    fn set_typed_and_get_typed_roundtrip_a_struct() {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let path = "./shit_typed.db";
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(format!("{path}.lock"));
+
+        let mut db = crate::open::<FileStorage<Json>>(path).unwrap();
+        db.set_typed("origin", &Point { x: 1, y: 2 }).unwrap();
+
+        let value: Option<Point> = db.get_typed("origin").unwrap();
+        assert_eq!(value, Some(Point { x: 1, y: 2 }));
+
+        let missing: Option<Point> = db.get_typed("nowhere").unwrap();
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn a_second_handle_cannot_clobber_a_flushed_one() {
+        let path = "./shit_lock.db";
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(format!("{path}.lock"));
+
+        let mut first = crate::open::<FileStorage<Json>>(path).unwrap();
+        first.set("a", "1").unwrap();
+        // `flush` renames a fresh file over `path`; the lock must not be
+        // tied to the now-replaced inode, or a second handle opened right
+        // after this would be free to race `first` and silently drop
+        // whichever of their writes loses.
+        first.flush().unwrap();
+
+        let second = crate::open::<FileStorage<Json>>(path);
+        assert!(matches!(second, Err(Error::Locked)));
+
+        drop(first);
+
+        let mut third = crate::open::<FileStorage<Json>>(path).unwrap();
+        assert_eq!(third.get("a"), Some("1"));
+        let _ = third.set("b", "2");
+    }
+
+    #[test]
+    fn flushing_a_smaller_payload_leaves_no_trailing_garbage() {
+        let path = "./shit_shrink.db";
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(format!("{path}.lock"));
+
+        let mut db = crate::open::<FileStorage<Json>>(path).unwrap();
+        db.set("big", "BBBBBBiiiiiigggggggggg").unwrap();
+        db.flush().unwrap();
+        drop(db);
+
+        // The write-to-temp-then-rename flush replaces the whole file rather
+        // than writing the new (shorter) payload in place, so a shrink can't
+        // leave stale bytes from the longer write past the new content's end.
+        let mut db = crate::open::<FileStorage<Json>>(path).unwrap();
+        db.del("big").unwrap();
+        db.set("small", "x").unwrap();
+        db.flush().unwrap();
+        drop(db);
+
+        let db = crate::open::<FileStorage<Json>>(path).unwrap();
+        assert_eq!(db.get("small"), Some("x"));
+        assert_eq!(db.get("big"), None);
+    }
+
+    #[test]
+    fn locked_error_reports_the_right_message() {
+        assert_eq!(
+            Error::Locked.to_string(),
+            "database file is locked by another handle"
+        );
+    }
+
+    #[test]
+    fn it_migrates_a_legacy_unversioned_file_on_open() {
+        let path = "./shit_legacy.db";
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(format!("{path}.lock"));
+
+        // Files written before versioning have no `KYIV` header at all: just
+        // the bare Format-encoded payload.
+        std::fs::write(path, br#"{"xixi":"haha"}"#).unwrap();
+
+        let mut db = crate::open::<JSONStorage>(path).unwrap();
+        assert_eq!(db.schema_version(), 0);
+        assert_eq!(db.get("xixi"), Some("haha"));
+
+        // Flushing rewrites it tagged with the current version.
+        db.flush().unwrap();
+        drop(db);
+
+        let db = crate::open::<JSONStorage>(path).unwrap();
+        assert_eq!(db.schema_version(), crate::CURRENT_VERSION);
+        assert_eq!(db.get("xixi"), Some("haha"));
+    }
+
+    #[test]
+    fn it_refuses_to_open_a_file_from_a_newer_version() {
+        let path = "./shit_future.db";
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(format!("{path}.lock"));
+
+        let mut bytes = b"KYIV".to_vec();
+        bytes.extend_from_slice(&(crate::CURRENT_VERSION + 1).to_le_bytes());
+        bytes.extend_from_slice(br#"{}"#);
+        std::fs::write(path, bytes).unwrap();
+
+        let result = crate::open::<JSONStorage>(path);
+        assert!(matches!(
+            result,
+            Err(Error::UnknownVersion(v)) if v == crate::CURRENT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn compressed_json_roundtrips_and_shrinks_on_disk() {
+        let path = "./shit_compressed.db";
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(format!("{path}.lock"));
+
+        let value = "haha".repeat(1000);
+        let mut db = crate::open::<FileStorage<Compressed<Json>>>(path).unwrap();
+        db.set("xixi", &value).unwrap();
+        db.flush().unwrap();
+        drop(db);
+
+        assert!(std::fs::metadata(path).unwrap().len() < value.len() as u64);
+
+        let db = crate::open::<FileStorage<Compressed<Json>>>(path).unwrap();
+        assert_eq!(db.get("xixi"), Some(value.as_str()));
+    }
+
+    #[test]
+    fn compressed_json_still_loads_an_uncompressed_legacy_file() {
+        let path = "./shit_compressed_legacy.db";
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(format!("{path}.lock"));
+
+        let mut db = crate::open::<FileStorage<Json>>(path).unwrap();
+        db.set("xixi", "haha").unwrap();
+        db.flush().unwrap();
+        drop(db);
+
+        let db = crate::open::<FileStorage<Compressed<Json>>>(path).unwrap();
+        assert_eq!(db.get("xixi"), Some("haha"));
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn sqlite_storage_persists_data() {
+        let path = "./shit.sqlite3";
+        let _ = std::fs::remove_file(path);
+
+        let mut db = crate::open::<crate::SqliteStorage>(path).unwrap();
+        db.set("xixi", "haha").unwrap();
+        db.flush().unwrap();
+        drop(db);
+
+        let db = crate::open::<crate::SqliteStorage>(path).unwrap();
+        assert_eq!(db.get("xixi"), Some("haha"));
+    }
+
+    #[test]
+    fn memory_storage_does_not_touch_disk() {
+        let mut db = crate::open::<MemoryStorage>("this path is never opened").unwrap();
+
+        let _ = db.set("xixi", "haha");
+        assert_eq!(db.get("xixi"), Some("haha"));
+
+        let _ = db.del("xixi");
+        assert_eq!(db.get("xixi"), None);
+    }
 }
@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use crate::{Error, Format};
+
+/// Bumped whenever the on-disk payload shape changes in a way old readers
+/// can't parse unassisted. Every file this crate writes is tagged with it
+/// via [`write_header`]; [`read_header`] recovers it on the way back in.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Prefixes every versioned payload so a header can be told apart from a
+/// pre-versioning file, which has none.
+const MAGIC: [u8; 4] = *b"KYIV";
+
+/// Produces the `BTreeMap` a stored payload decodes to, whatever on-disk
+/// version it was written at.
+///
+/// Implemented for every [`Format`] so `FileStorage<F>` can migrate whatever
+/// it finds on open. A future version that changes the payload shape gets
+/// its own arm here, not a new trait impl.
+pub trait Migration {
+    fn migrate(version: u32, raw: &[u8]) -> Result<BTreeMap<String, String>, Error>;
+}
+
+impl<F: Format> Migration for F {
+    fn migrate(version: u32, raw: &[u8]) -> Result<BTreeMap<String, String>, Error> {
+        match version {
+            // Version 0 (no header, predates this feature) and the current
+            // version both hold a plain Format-encoded payload; they just
+            // happen to parse the same way today.
+            0..=CURRENT_VERSION => F::deserialize::<BTreeMap<String, String>>(raw),
+            v => Err(Error::UnknownVersion(v)),
+        }
+    }
+}
+
+/// Splits a file's bytes into `(version, payload)`. Files without the
+/// `MAGIC` prefix predate versioning and are treated as version 0.
+pub fn read_header(bytes: &[u8]) -> (u32, &[u8]) {
+    if bytes.len() < MAGIC.len() + 4 || bytes[..MAGIC.len()] != MAGIC {
+        return (0, bytes);
+    }
+    let mut version_bytes = [0u8; 4];
+    version_bytes.copy_from_slice(&bytes[MAGIC.len()..MAGIC.len() + 4]);
+    (u32::from_le_bytes(version_bytes), &bytes[MAGIC.len() + 4..])
+}
+
+/// Prepends the current version header to a serialized payload.
+pub fn write_header(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(MAGIC.len() + 4 + payload.len());
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&CURRENT_VERSION.to_le_bytes());
+    out.extend_from_slice(payload);
+    out
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use fs2::FileExt;
+use tempfile::NamedTempFile;
+
+use crate::{migration, Error, Format, Migration, Storage};
+
+/// A file-backed `Storage` generic over the on-disk `Format`.
+///
+/// The encoding is a type parameter so `open::<FileStorage<Ron>>(path)` or
+/// `..::<Bincode>` work without touching any of the read/write logic below.
+///
+/// `from` takes an exclusive advisory lock on a `path.lock` sentinel file
+/// and holds it for as long as the `FileStorage` is alive, returning
+/// `Error::Locked` if another handle already holds it rather than blocking
+/// forever. The lock lives on a separate file (not `path` itself) because
+/// `flush` never writes `path` in place: it serializes to a sibling temp
+/// file, `fsync`s it, then renames it over `path`, so a crash mid-write
+/// leaves either the old or the new complete database, never a truncated
+/// one. Locking `path` directly would have that rename swap the on-disk
+/// file out from under the lock, since a rename replaces the directory
+/// entry's inode rather than the one the lock's file descriptor refers to.
+pub struct FileStorage<F: Format> {
+    // Never read: it exists only to hold the advisory lock open for the
+    // lifetime of this `FileStorage`, releasing it on `Drop` when the `File`
+    // closes.
+    #[allow(dead_code)]
+    lock_file: File,
+    path: String,
+    data: BTreeMap<String, String>,
+    version: u32,
+    _format: PhantomData<F>,
+}
+
+impl<F: Format> Debug for FileStorage<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileStorage")
+            .field("path", &self.path)
+            .field("data", &self.data)
+            .finish()
+    }
+}
+
+fn lock_path(path: &str) -> String {
+    format!("{path}.lock")
+}
+
+impl<F: Format> Storage for FileStorage<F> {
+    fn from(source: &str) -> Result<FileStorage<F>, Error> {
+        let lock_file = File::options()
+            .create(true)
+            // Existing lock sentinel content (there never should be any) is
+            // irrelevant; only its fd is used, to hold an advisory lock.
+            .truncate(false)
+            .write(true)
+            .open(lock_path(source))?;
+        lock_file.try_lock_exclusive().map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock => Error::Locked,
+            _ => Error::from(e),
+        })?;
+
+        let mut data_file = File::options()
+            .create(true)
+            // An existing database's content must be read, not discarded.
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(source)?;
+        let mut bytes = Vec::new();
+        data_file.read_to_end(&mut bytes)?;
+        let (version, data) = if bytes.is_empty() {
+            (crate::CURRENT_VERSION, BTreeMap::new())
+        } else {
+            let (version, payload) = migration::read_header(&bytes);
+            (version, F::migrate(version, payload)?)
+        };
+
+        Ok(FileStorage {
+            lock_file,
+            path: source.to_string(),
+            data,
+            version,
+            _format: PhantomData,
+        })
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        let payload = F::serialize(&self.data)?;
+        let content = migration::write_header(&payload);
+
+        let dir = Path::new(&self.path).parent().filter(|p| !p.as_os_str().is_empty());
+        let mut tmp = match dir {
+            Some(dir) => NamedTempFile::new_in(dir)?,
+            None => NamedTempFile::new_in(".")?,
+        };
+        tmp.write_all(&content)?;
+        tmp.as_file().sync_all()?;
+        tmp.persist(&self.path).map_err(|e| Error::from(e.error))?;
+        self.version = crate::CURRENT_VERSION;
+
+        Ok(())
+    }
+
+    fn schema_version(&self) -> u32 {
+        self.version
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        Some(self.data.get(key)?.as_str())
+    }
+
+    fn del(&mut self, key: &str) -> Result<(), Error> {
+        self.data.remove(key);
+        Ok(())
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        self.data.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
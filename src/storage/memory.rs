@@ -0,0 +1,37 @@
+use std::collections::BTreeMap;
+
+use crate::{Error, Storage};
+
+/// An ephemeral, file-less `Storage` for tests and short-lived use.
+///
+/// `from` ignores its `source` argument entirely (nothing is opened or
+/// created on disk) and `flush` is a no-op, since there's nothing to
+/// persist to.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    data: BTreeMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn from(_source: &str) -> Result<Self, Error> {
+        Ok(MemoryStorage::default())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        Some(self.data.get(key)?.as_str())
+    }
+
+    fn del(&mut self, key: &str) -> Result<(), Error> {
+        self.data.remove(key);
+        Ok(())
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        self.data.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
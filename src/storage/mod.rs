@@ -0,0 +1,9 @@
+mod file;
+mod memory;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use file::FileStorage;
+pub use memory::MemoryStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use rusqlite::{params, Connection};
+
+use crate::{Error, Storage};
+
+/// A `Storage` backed by a single SQLite table (`kv(key, value)`).
+///
+/// Gated behind the `sqlite` cargo feature so the core crate stays
+/// dependency-light for users who only need the file or in-memory backends.
+/// Like `FileStorage`, reads and writes work against an in-memory cache;
+/// `flush` is what translates it into prepared statements and commits them.
+pub struct SqliteStorage {
+    conn: Connection,
+    cache: BTreeMap<String, String>,
+}
+
+impl Storage for SqliteStorage {
+    fn from(source: &str) -> Result<Self, Error> {
+        let conn = Connection::open(source).map_err(sqlite_error)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS kv (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            [],
+        )
+        .map_err(sqlite_error)?;
+
+        let mut cache = BTreeMap::new();
+        let mut stmt = conn.prepare("SELECT key, value FROM kv").map_err(sqlite_error)?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(sqlite_error)?;
+        for row in rows {
+            let (key, value): (String, String) = row.map_err(sqlite_error)?;
+            cache.insert(key, value);
+        }
+        drop(stmt);
+
+        Ok(SqliteStorage { conn, cache })
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        let tx = self.conn.transaction().map_err(sqlite_error)?;
+        tx.execute("DELETE FROM kv", []).map_err(sqlite_error)?;
+        {
+            let mut stmt = tx
+                .prepare("INSERT INTO kv (key, value) VALUES (?1, ?2)")
+                .map_err(sqlite_error)?;
+            for (key, value) in &self.cache {
+                stmt.execute(params![key, value]).map_err(sqlite_error)?;
+            }
+        }
+        tx.commit().map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        Some(self.cache.get(key)?.as_str())
+    }
+
+    fn del(&mut self, key: &str) -> Result<(), Error> {
+        self.cache.remove(key);
+        Ok(())
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        self.cache.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+fn sqlite_error(e: rusqlite::Error) -> Error {
+    Error::Io(io::Error::other(e))
+}